@@ -14,6 +14,18 @@ pub enum Error {
     InvalidBeforeAfter(DateTime<Utc>, DateTime<Utc>),
     #[error("TickerSpec `All` can only be used with the `Dollars` and `Shares` `AmountSpec`s")]
     InvalidCombination,
+    #[error("`OrderType` prices must be positive. Got: {0}")]
+    NonPositivePrice(Decimal),
+    #[error("Computing a trade delta from a `Dollars` amount requires a `decision_price` or a `reference_price`")]
+    MissingReferencePrice,
+    #[error("Computing a trade delta from a `Percent` amount requires the caller's `equity`")]
+    MissingEquity,
+    #[error("Expanding into per-ticker intents requires a `TickerSpec::All`/`Percent` intent. Got ticker {0:?} and amount {1:?}")]
+    NotExpandable(TickerSpec, AmountSpec),
+    #[error("Expanding into per-ticker intents requires at least one positive ticker weight")]
+    EmptyWeights,
+    #[error("Ticker weights must be non-negative. Got: {0}")]
+    NegativeWeight(Decimal),
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -34,6 +46,122 @@ pub enum UpdatePolicy {
     Update,
 }
 
+/// What the high-water/low-water mark for a [`TrailingStop`] is tracked against: the
+/// `decision_price` recorded at intent creation time, or the live price observed by the
+/// order-manager.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TrailingStopAnchor {
+    DecisionPrice,
+    LivePrice,
+}
+
+/// A trailing-stop specification, mirroring the `TSLPAMT`/`TSLPPCT` order types some brokers
+/// expose: the stop follows the high-water mark (for a long) or low-water mark (for a short) by
+/// a fixed amount or percent, ratcheting favorably as that mark moves but never loosening. The
+/// `anchor` is folded into each variant (rather than a second, independently-settable field) so
+/// a trailing stop can't exist without the anchor the order-manager needs to reconstruct its
+/// trigger.
+///
+/// For a long, the effective stop is `high_water_mark - amount` for `Amount`, or
+/// `high_water_mark * (1 - percent / 100)` for `Percent`. For a short, it is
+/// `low_water_mark + amount` or `low_water_mark * (1 + percent / 100)` respectively.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TrailingStop {
+    Amount {
+        amount: Decimal,
+        anchor: TrailingStopAnchor,
+    },
+    Percent {
+        percent: Decimal,
+        anchor: TrailingStopAnchor,
+    },
+}
+
+/// The kind of order an intent should be executed as, and the prices each kind requires. This
+/// replaces the old free-floating `limit_price`/`stop_price` fields so an intent can't express a
+/// nonsensical combination (e.g. a limit price with no limit order) and so consumers can tell a
+/// plain market order apart from a stop-limit or a market/limit-if-touched (MIT/LIT) trigger.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderType {
+    #[default]
+    Market,
+    Limit {
+        limit_price: Decimal,
+    },
+    Stop {
+        stop_price: Decimal,
+    },
+    StopLimit {
+        stop_price: Decimal,
+        limit_price: Decimal,
+    },
+    MarketIfTouched {
+        trigger_price: Decimal,
+    },
+    LimitIfTouched {
+        trigger_price: Decimal,
+        limit_price: Decimal,
+    },
+}
+
+impl OrderType {
+    fn prices(&self) -> Vec<Decimal> {
+        match self {
+            OrderType::Market => vec![],
+            OrderType::Limit { limit_price } => vec![*limit_price],
+            OrderType::Stop { stop_price } => vec![*stop_price],
+            OrderType::StopLimit {
+                stop_price,
+                limit_price,
+            } => vec![*stop_price, *limit_price],
+            OrderType::MarketIfTouched { trigger_price } => vec![*trigger_price],
+            OrderType::LimitIfTouched {
+                trigger_price,
+                limit_price,
+            } => vec![*trigger_price, *limit_price],
+        }
+    }
+}
+
+/// Which side of the market a [`TradeDelta`] needs to trade.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+/// The concrete order needed to move a holding from its current quantity to a
+/// [`PositionIntent`]'s target, as computed by [`PositionIntent::diff`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TradeDelta {
+    pub side: Side,
+    pub qty: Decimal,
+    /// `true` when the target flips the position through zero (e.g. current `+100`, target
+    /// `-50`), so the executor may need to split the order into a close followed by an open if
+    /// the venue can't flip the position atomically.
+    pub crosses_zero: bool,
+}
+
+fn round_to_increment(value: Decimal, increment: Decimal) -> Decimal {
+    (value / increment).round() * increment
+}
+
+fn floor_to_increment(value: Decimal, increment: Decimal) -> Decimal {
+    (value / increment).floor() * increment
+}
+
+fn ceil_to_increment(value: Decimal, increment: Decimal) -> Decimal {
+    (value / increment).ceil() * increment
+}
+
+fn truncate_to_increment(value: Decimal, increment: Decimal) -> Decimal {
+    (value / increment).trunc() * increment
+}
+
 impl AmountSpec {
     pub fn merge(self, other: Self) -> Result<Self, Error> {
         match (self, other) {
@@ -69,8 +197,8 @@ pub struct PositionIntentBuilder {
     amount: AmountSpec,
     update_policy: UpdatePolicy,
     decision_price: Option<Decimal>,
-    limit_price: Option<Decimal>,
-    stop_price: Option<Decimal>,
+    order_type: OrderType,
+    trailing_stop: Option<TrailingStop>,
     before: Option<DateTime<Utc>>,
     after: Option<DateTime<Utc>>,
 }
@@ -86,13 +214,49 @@ impl PositionIntentBuilder {
         self
     }
 
-    pub fn limit_price(mut self, limit_price: Decimal) -> Self {
-        self.limit_price = Some(limit_price);
+    pub fn order_type(mut self, order_type: OrderType) -> Self {
+        self.order_type = order_type;
+        self
+    }
+
+    pub fn market(mut self) -> Self {
+        self.order_type = OrderType::Market;
+        self
+    }
+
+    pub fn limit(mut self, limit_price: Decimal) -> Self {
+        self.order_type = OrderType::Limit { limit_price };
         self
     }
 
-    pub fn stop_price(mut self, stop_price: Decimal) -> Self {
-        self.stop_price = Some(stop_price);
+    pub fn stop(mut self, stop_price: Decimal) -> Self {
+        self.order_type = OrderType::Stop { stop_price };
+        self
+    }
+
+    pub fn stop_limit(mut self, stop_price: Decimal, limit_price: Decimal) -> Self {
+        self.order_type = OrderType::StopLimit {
+            stop_price,
+            limit_price,
+        };
+        self
+    }
+
+    pub fn market_if_touched(mut self, trigger_price: Decimal) -> Self {
+        self.order_type = OrderType::MarketIfTouched { trigger_price };
+        self
+    }
+
+    pub fn limit_if_touched(mut self, trigger_price: Decimal, limit_price: Decimal) -> Self {
+        self.order_type = OrderType::LimitIfTouched {
+            trigger_price,
+            limit_price,
+        };
+        self
+    }
+
+    pub fn trailing_stop(mut self, trailing_stop: TrailingStop) -> Self {
+        self.trailing_stop = Some(trailing_stop);
         self
     }
 
@@ -122,6 +286,14 @@ impl PositionIntentBuilder {
             (TickerSpec::All, AmountSpec::Shares(_)) => return Err(Error::InvalidCombination),
             _ => (),
         }
+        if let Some(price) = self
+            .order_type
+            .prices()
+            .into_iter()
+            .find(|price| *price <= Decimal::ZERO)
+        {
+            return Err(Error::NonPositivePrice(price));
+        }
         Ok(PositionIntent {
             id: Uuid::new_v4(),
             strategy: self.strategy,
@@ -131,8 +303,8 @@ impl PositionIntentBuilder {
             amount: self.amount,
             update_policy: self.update_policy,
             decision_price: self.decision_price,
-            limit_price: self.limit_price,
-            stop_price: self.stop_price,
+            order_type: self.order_type,
+            trailing_stop: self.trailing_stop,
             before: self.before,
             after: self.after,
         })
@@ -159,10 +331,14 @@ pub struct PositionIntent {
     /// translating between dollars and shares by the order-manager.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub decision_price: Option<Decimal>,
+    /// The kind of order this intent should be executed as, and the prices it requires. Defaults
+    /// to `OrderType::Market` when unset by the builder.
+    pub order_type: OrderType,
+    /// Trailing-stop parameters, if this intent's stop should ratchet with the market instead of
+    /// sitting at a fixed `stop_price`. See [`TrailingStop`] for the trigger-price formula and
+    /// its bundled anchor.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub limit_price: Option<Decimal>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub stop_price: Option<Decimal>,
+    pub trailing_stop: Option<TrailingStop>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub before: Option<DateTime<Utc>>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -182,12 +358,249 @@ impl PositionIntent {
             amount,
             update_policy: UpdatePolicy::Update,
             decision_price: None,
-            limit_price: None,
-            stop_price: None,
+            order_type: OrderType::default(),
+            trailing_stop: None,
             before: None,
             after: None,
         }
     }
+
+    /// Collapses many intents into net per-`(strategy, sub_strategy, ticker)` targets, the way
+    /// an order-manager otherwise has to reimplement by hand when several intents arrive for the
+    /// same sub-strategy and ticker.
+    ///
+    /// Intents are folded in `timestamp` order (intents with equal timestamps keep their iteration
+    /// order, so the later one is folded in as the incoming intent). `UpdatePolicy::Update` merges the incoming amount into the running
+    /// target via [`AmountSpec::merge`]; `Retain` drops the incoming intent once a target
+    /// already exists; `RetainLong`/`RetainShort` keep the running target only while it is
+    /// long/short (by the sign of `Dollars`/`Shares`/`Percent`), and otherwise take the incoming
+    /// intent outright. `before`/`after` windows are carried forward from whichever side has
+    /// them set.
+    pub fn net(
+        intents: impl IntoIterator<Item = PositionIntent>,
+    ) -> Result<Vec<PositionIntent>, Error> {
+        let mut ordered: Vec<PositionIntent> = intents.into_iter().collect();
+        ordered.sort_by_key(|intent| intent.timestamp);
+
+        let mut targets: Vec<PositionIntent> = Vec::new();
+        for intent in ordered {
+            let existing = targets.iter_mut().find(|target| {
+                target.strategy == intent.strategy
+                    && target.sub_strategy == intent.sub_strategy
+                    && target.ticker == intent.ticker
+            });
+            match existing {
+                None => targets.push(intent),
+                Some(target) => *target = Self::fold_targets(target.clone(), intent)?,
+            }
+        }
+        Ok(targets)
+    }
+
+    fn fold_targets(
+        prior: PositionIntent,
+        incoming: PositionIntent,
+    ) -> Result<PositionIntent, Error> {
+        fn is_long(amount: &AmountSpec) -> bool {
+            matches!(amount, AmountSpec::Dollars(x) | AmountSpec::Shares(x) | AmountSpec::Percent(x) if *x > Decimal::ZERO)
+        }
+        fn is_short(amount: &AmountSpec) -> bool {
+            matches!(amount, AmountSpec::Dollars(x) | AmountSpec::Shares(x) | AmountSpec::Percent(x) if *x < Decimal::ZERO)
+        }
+
+        let before = incoming.before.or(prior.before);
+        let after = incoming.after.or(prior.after);
+        if let Some((before, after)) = before.zip(after) {
+            if before < after {
+                return Err(Error::InvalidBeforeAfter(before, after));
+            }
+        }
+
+        let winner = match incoming.update_policy {
+            UpdatePolicy::Update => {
+                let amount = prior.amount.clone().merge(incoming.amount.clone())?;
+                PositionIntent { amount, ..incoming }
+            }
+            UpdatePolicy::Retain => prior,
+            UpdatePolicy::RetainLong if is_long(&prior.amount) => prior,
+            UpdatePolicy::RetainShort if is_short(&prior.amount) => prior,
+            UpdatePolicy::RetainLong | UpdatePolicy::RetainShort => incoming,
+        };
+
+        Ok(PositionIntent {
+            before,
+            after,
+            ..winner
+        })
+    }
+
+    /// Computes the order needed to move a holding of `current_qty` shares to this intent's
+    /// target, as `TradeDelta { side, qty, crosses_zero }`.
+    ///
+    /// `Shares` targets are compared to `current_qty` directly. `Dollars` targets are converted
+    /// to shares using `decision_price`, falling back to `reference_price` if unset, erroring via
+    /// `Error::MissingReferencePrice` if neither is available. `Percent` targets additionally
+    /// require the caller's account `equity` to translate the percentage into a dollar amount,
+    /// erroring via `Error::MissingEquity` if it is not provided. A `Zero` target is a full
+    /// liquidation of `current_qty`.
+    pub fn diff(
+        &self,
+        current_qty: Decimal,
+        reference_price: Option<Decimal>,
+        equity: Option<Decimal>,
+    ) -> Result<TradeDelta, Error> {
+        let price = || {
+            self.decision_price
+                .or(reference_price)
+                .ok_or(Error::MissingReferencePrice)
+        };
+        let target_qty = match &self.amount {
+            AmountSpec::Shares(qty) => *qty,
+            AmountSpec::Dollars(dollars) => dollars / price()?,
+            AmountSpec::Percent(percent) => {
+                let equity = equity.ok_or(Error::MissingEquity)?;
+                (equity * percent / Decimal::new(100, 0)) / price()?
+            }
+            AmountSpec::Zero => Decimal::ZERO,
+        };
+
+        let delta = target_qty - current_qty;
+        let side = if delta >= Decimal::ZERO {
+            Side::Buy
+        } else {
+            Side::Sell
+        };
+        let crosses_zero = (current_qty > Decimal::ZERO && target_qty < Decimal::ZERO)
+            || (current_qty < Decimal::ZERO && target_qty > Decimal::ZERO);
+
+        Ok(TradeDelta {
+            side,
+            qty: delta.abs(),
+            crosses_zero,
+        })
+    }
+
+    /// Expands a `TickerSpec::All`/`Percent` intent (e.g. "hold 30% spread across these names")
+    /// into one concrete `Percent` intent per ticker, distributing the original percent linearly
+    /// by `weights`: ticker `i` gets `percent * weight_i / sum(weights)`. `strategy`,
+    /// `update_policy`, `order_type`, and the `before`/`after` window are preserved on each
+    /// expanded intent. Errors if `weights` is empty, any weight is negative, or the intent is
+    /// not itself an `All`/`Percent` intent.
+    pub fn expand(
+        &self,
+        weights: impl IntoIterator<Item = (String, Decimal)>,
+    ) -> Result<Vec<PositionIntent>, Error> {
+        let percent = match (&self.ticker, &self.amount) {
+            (TickerSpec::All, AmountSpec::Percent(percent)) => *percent,
+            _ => {
+                return Err(Error::NotExpandable(
+                    self.ticker.clone(),
+                    self.amount.clone(),
+                ))
+            }
+        };
+
+        let weights: Vec<(String, Decimal)> = weights.into_iter().collect();
+        if let Some((_, weight)) = weights.iter().find(|(_, weight)| *weight < Decimal::ZERO) {
+            return Err(Error::NegativeWeight(*weight));
+        }
+        let total_weight: Decimal = weights.iter().map(|(_, weight)| *weight).sum();
+        if total_weight <= Decimal::ZERO {
+            return Err(Error::EmptyWeights);
+        }
+
+        Ok(weights
+            .into_iter()
+            .map(|(ticker, weight)| PositionIntent {
+                id: Uuid::new_v4(),
+                strategy: self.strategy.clone(),
+                sub_strategy: self.sub_strategy.clone(),
+                timestamp: self.timestamp,
+                ticker: TickerSpec::Ticker(ticker),
+                amount: AmountSpec::Percent(percent * weight / total_weight),
+                update_policy: self.update_policy.clone(),
+                decision_price: None,
+                order_type: self.order_type.clone(),
+                trailing_stop: None,
+                before: self.before,
+                after: self.after,
+            })
+            .collect())
+    }
+
+    /// Convenience wrapper over [`PositionIntent::expand`] that distributes the percent equally
+    /// across `tickers`.
+    pub fn expand_equal_weight(
+        &self,
+        tickers: impl IntoIterator<Item = String>,
+    ) -> Result<Vec<PositionIntent>, Error> {
+        self.expand(
+            tickers
+                .into_iter()
+                .map(|ticker| (ticker, Decimal::new(1, 0))),
+        )
+    }
+
+    /// Rounds this intent's prices to multiples of `tick_size` and its `Shares` amount to a
+    /// multiple of `lot_size`, returning a new intent and leaving `self`
+    /// untouched so callers can defer quantization until they know the venue's increments.
+    ///
+    /// `decision_price` and stop/trigger prices round to the nearest tick. Limit prices round in
+    /// the passive/favorable direction instead: down for a `Side::Buy`, up for a `Side::Sell`.
+    /// `side` is the actual trade direction (e.g. from [`PositionIntent::diff`]) rather than the
+    /// sign of the target amount, since a target's sign doesn't tell you whether reaching it
+    /// means buying or selling (reducing a long, flipping to a short, and liquidating to `Zero`
+    /// are all sells of a currently-long position, for instance). `Shares` amounts truncate toward
+    /// zero so we never round up into an order the account can't afford.
+    pub fn quantize(&self, side: Side, tick_size: Decimal, lot_size: Decimal) -> PositionIntent {
+        let favors_buy = side == Side::Buy;
+        let quantize_price = |price: Decimal| round_to_increment(price, tick_size);
+        let quantize_favorable = |price: Decimal| {
+            if favors_buy {
+                floor_to_increment(price, tick_size)
+            } else {
+                ceil_to_increment(price, tick_size)
+            }
+        };
+
+        let order_type = match self.order_type.clone() {
+            OrderType::Market => OrderType::Market,
+            OrderType::Limit { limit_price } => OrderType::Limit {
+                limit_price: quantize_favorable(limit_price),
+            },
+            OrderType::Stop { stop_price } => OrderType::Stop {
+                stop_price: quantize_price(stop_price),
+            },
+            OrderType::StopLimit {
+                stop_price,
+                limit_price,
+            } => OrderType::StopLimit {
+                stop_price: quantize_price(stop_price),
+                limit_price: quantize_favorable(limit_price),
+            },
+            OrderType::MarketIfTouched { trigger_price } => OrderType::MarketIfTouched {
+                trigger_price: quantize_price(trigger_price),
+            },
+            OrderType::LimitIfTouched {
+                trigger_price,
+                limit_price,
+            } => OrderType::LimitIfTouched {
+                trigger_price: quantize_price(trigger_price),
+                limit_price: quantize_favorable(limit_price),
+            },
+        };
+        let amount = match &self.amount {
+            AmountSpec::Shares(qty) => AmountSpec::Shares(truncate_to_increment(*qty, lot_size)),
+            other => other.clone(),
+        };
+
+        PositionIntent {
+            decision_price: self.decision_price.map(quantize_price),
+            order_type,
+            amount,
+            ..self.clone()
+        }
+    }
 }
 
 #[cfg(test)]
@@ -201,8 +614,7 @@ mod test {
         let _intent = builder
             .sub_strategy("B")
             .decision_price(Decimal::new(2, 0))
-            .limit_price(Decimal::new(3, 0))
-            .stop_price(Decimal::new(3, 0))
+            .stop_limit(Decimal::new(3, 0), Decimal::new(3, 0))
             .update_policy(UpdatePolicy::Retain)
             .before(Utc::now() + Duration::hours(1))
             .after(Utc::now())
@@ -216,8 +628,11 @@ mod test {
         let intent = builder
             .sub_strategy("B")
             .decision_price(Decimal::new(2, 0))
-            .limit_price(Decimal::new(3, 0))
-            .stop_price(Decimal::new(3, 0))
+            .stop_limit(Decimal::new(3, 0), Decimal::new(3, 0))
+            .trailing_stop(TrailingStop::Percent {
+                percent: Decimal::new(5, 0),
+                anchor: TrailingStopAnchor::LivePrice,
+            })
             .update_policy(UpdatePolicy::Retain)
             .before(Utc::now() + Duration::hours(1))
             .after(Utc::now())
@@ -227,4 +642,262 @@ mod test {
         let deserialized = serde_json::from_str(&serialized).unwrap();
         assert_eq!(intent, deserialized);
     }
+
+    #[test]
+    fn trailing_stop_defaults_to_unset() {
+        let intent = PositionIntent::builder("A", "AAPL", AmountSpec::Shares(Decimal::new(1, 0)))
+            .build()
+            .unwrap();
+        assert_eq!(intent.trailing_stop, None);
+    }
+
+    #[test]
+    fn builder_sets_trailing_stop_with_its_anchor() {
+        let intent = PositionIntent::builder("A", "AAPL", AmountSpec::Shares(Decimal::new(1, 0)))
+            .trailing_stop(TrailingStop::Amount {
+                amount: Decimal::new(2, 0),
+                anchor: TrailingStopAnchor::DecisionPrice,
+            })
+            .build()
+            .unwrap();
+        assert_eq!(
+            intent.trailing_stop,
+            Some(TrailingStop::Amount {
+                amount: Decimal::new(2, 0),
+                anchor: TrailingStopAnchor::DecisionPrice,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_non_positive_order_type_prices() {
+        let builder = PositionIntent::builder("A", "AAPL", AmountSpec::Shares(Decimal::new(1, 0)));
+        let err = builder.limit(Decimal::new(0, 0)).build().unwrap_err();
+        assert!(matches!(err, Error::NonPositivePrice(_)));
+    }
+
+    #[test]
+    fn net_merges_update_policy_amounts() {
+        let first = PositionIntent::builder("A", "AAPL", AmountSpec::Dollars(Decimal::new(100, 0)))
+            .build()
+            .unwrap();
+        let second = PositionIntent::builder("A", "AAPL", AmountSpec::Dollars(Decimal::new(50, 0)))
+            .build()
+            .unwrap();
+        let netted = PositionIntent::net(vec![first, second]).unwrap();
+        assert_eq!(netted.len(), 1);
+        assert_eq!(netted[0].amount, AmountSpec::Dollars(Decimal::new(150, 0)));
+    }
+
+    #[test]
+    fn net_retain_drops_incoming_intent() {
+        let first = PositionIntent::builder("A", "AAPL", AmountSpec::Dollars(Decimal::new(100, 0)))
+            .build()
+            .unwrap();
+        let second = PositionIntent::builder("A", "AAPL", AmountSpec::Dollars(Decimal::new(50, 0)))
+            .update_policy(UpdatePolicy::Retain)
+            .build()
+            .unwrap();
+        let netted = PositionIntent::net(vec![first, second]).unwrap();
+        assert_eq!(netted.len(), 1);
+        assert_eq!(netted[0].amount, AmountSpec::Dollars(Decimal::new(100, 0)));
+    }
+
+    #[test]
+    fn net_retain_long_takes_new_when_prior_is_short() {
+        let first =
+            PositionIntent::builder("A", "AAPL", AmountSpec::Dollars(Decimal::new(-100, 0)))
+                .build()
+                .unwrap();
+        let second = PositionIntent::builder("A", "AAPL", AmountSpec::Dollars(Decimal::new(50, 0)))
+            .update_policy(UpdatePolicy::RetainLong)
+            .build()
+            .unwrap();
+        let netted = PositionIntent::net(vec![first, second]).unwrap();
+        assert_eq!(netted.len(), 1);
+        assert_eq!(netted[0].amount, AmountSpec::Dollars(Decimal::new(50, 0)));
+    }
+
+    #[test]
+    fn net_rejects_combined_before_after_window_that_would_be_invalid() {
+        let now = Utc::now();
+        let first = PositionIntent::builder("A", "AAPL", AmountSpec::Dollars(Decimal::new(100, 0)))
+            .before(now + Duration::hours(1))
+            .build()
+            .unwrap();
+        let second = PositionIntent::builder("A", "AAPL", AmountSpec::Dollars(Decimal::new(50, 0)))
+            .after(now + Duration::hours(3))
+            .build()
+            .unwrap();
+        let err = PositionIntent::net(vec![first, second]).unwrap_err();
+        assert!(matches!(err, Error::InvalidBeforeAfter(_, _)));
+    }
+
+    #[test]
+    fn diff_shares_flags_crossing_zero() {
+        let intent = PositionIntent::builder("A", "AAPL", AmountSpec::Shares(Decimal::new(-50, 0)))
+            .build()
+            .unwrap();
+        let delta = intent.diff(Decimal::new(100, 0), None, None).unwrap();
+        assert_eq!(delta.side, Side::Sell);
+        assert_eq!(delta.qty, Decimal::new(150, 0));
+        assert!(delta.crosses_zero);
+    }
+
+    #[test]
+    fn diff_zero_target_is_full_liquidation() {
+        let intent = PositionIntent::builder("A", "AAPL", AmountSpec::Zero)
+            .build()
+            .unwrap();
+        let delta = intent.diff(Decimal::new(100, 0), None, None).unwrap();
+        assert_eq!(delta.side, Side::Sell);
+        assert_eq!(delta.qty, Decimal::new(100, 0));
+        assert!(!delta.crosses_zero);
+    }
+
+    #[test]
+    fn diff_dollars_requires_a_reference_price() {
+        let intent =
+            PositionIntent::builder("A", "AAPL", AmountSpec::Dollars(Decimal::new(100, 0)))
+                .build()
+                .unwrap();
+        let err = intent.diff(Decimal::ZERO, None, None).unwrap_err();
+        assert!(matches!(err, Error::MissingReferencePrice));
+    }
+
+    #[test]
+    fn diff_percent_requires_equity() {
+        let intent = PositionIntent::builder("A", "AAPL", AmountSpec::Percent(Decimal::new(50, 0)))
+            .build()
+            .unwrap();
+        let err = intent
+            .diff(Decimal::ZERO, Some(Decimal::new(10, 0)), None)
+            .unwrap_err();
+        assert!(matches!(err, Error::MissingEquity));
+    }
+
+    #[test]
+    fn expand_distributes_percent_by_weight() {
+        let intent = PositionIntent::builder(
+            "A",
+            TickerSpec::All,
+            AmountSpec::Percent(Decimal::new(30, 0)),
+        )
+        .build()
+        .unwrap();
+        let expanded = intent
+            .expand(vec![
+                ("AAPL".to_string(), Decimal::new(1, 0)),
+                ("MSFT".to_string(), Decimal::new(2, 0)),
+            ])
+            .unwrap();
+        assert_eq!(expanded.len(), 2);
+        assert_eq!(expanded[0].ticker, TickerSpec::Ticker("AAPL".to_string()));
+        assert_eq!(expanded[0].amount, AmountSpec::Percent(Decimal::new(10, 0)));
+        assert_eq!(expanded[1].amount, AmountSpec::Percent(Decimal::new(20, 0)));
+    }
+
+    #[test]
+    fn expand_carries_order_type_through_to_each_leg() {
+        let intent = PositionIntent::builder(
+            "A",
+            TickerSpec::All,
+            AmountSpec::Percent(Decimal::new(30, 0)),
+        )
+        .limit(Decimal::new(10, 0))
+        .build()
+        .unwrap();
+        let expanded = intent
+            .expand(vec![("AAPL".to_string(), Decimal::new(1, 0))])
+            .unwrap();
+        assert_eq!(
+            expanded[0].order_type,
+            OrderType::Limit {
+                limit_price: Decimal::new(10, 0)
+            }
+        );
+    }
+
+    #[test]
+    fn expand_rejects_non_all_percent_intents() {
+        let intent = PositionIntent::builder("A", "AAPL", AmountSpec::Percent(Decimal::new(30, 0)))
+            .build()
+            .unwrap();
+        let err = intent
+            .expand(vec![("MSFT".to_string(), Decimal::new(1, 0))])
+            .unwrap_err();
+        assert!(matches!(err, Error::NotExpandable(_, _)));
+    }
+
+    #[test]
+    fn expand_rejects_negative_weights() {
+        let intent = PositionIntent::builder(
+            "A",
+            TickerSpec::All,
+            AmountSpec::Percent(Decimal::new(30, 0)),
+        )
+        .build()
+        .unwrap();
+        let err = intent
+            .expand(vec![("AAPL".to_string(), Decimal::new(-1, 0))])
+            .unwrap_err();
+        assert!(matches!(err, Error::NegativeWeight(_)));
+    }
+
+    #[test]
+    fn quantize_rounds_shares_down_to_lot_size() {
+        let intent =
+            PositionIntent::builder("A", "AAPL", AmountSpec::Shares(Decimal::new(1049, 1)))
+                .build()
+                .unwrap();
+        let quantized = intent.quantize(Side::Buy, Decimal::new(1, 2), Decimal::new(10, 0));
+        assert_eq!(quantized.amount, AmountSpec::Shares(Decimal::new(100, 0)));
+    }
+
+    #[test]
+    fn quantize_rounds_buy_limit_price_down() {
+        let intent = PositionIntent::builder("A", "AAPL", AmountSpec::Shares(Decimal::new(10, 0)))
+            .limit(Decimal::new(10129, 3))
+            .build()
+            .unwrap();
+        let quantized = intent.quantize(Side::Buy, Decimal::new(1, 2), Decimal::new(1, 0));
+        assert_eq!(
+            quantized.order_type,
+            OrderType::Limit {
+                limit_price: Decimal::new(1012, 2)
+            }
+        );
+    }
+
+    #[test]
+    fn quantize_rounds_sell_limit_price_up() {
+        let intent = PositionIntent::builder("A", "AAPL", AmountSpec::Shares(Decimal::new(-10, 0)))
+            .limit(Decimal::new(10129, 3))
+            .build()
+            .unwrap();
+        let quantized = intent.quantize(Side::Sell, Decimal::new(1, 2), Decimal::new(1, 0));
+        assert_eq!(
+            quantized.order_type,
+            OrderType::Limit {
+                limit_price: Decimal::new(1013, 2)
+            }
+        );
+    }
+
+    #[test]
+    fn quantize_uses_side_not_target_amount_sign() {
+        // Reducing a long (still a long target) is a sell; quantize must round the
+        // limit price by the actual trade side, not by the sign of the target amount.
+        let intent = PositionIntent::builder("A", "AAPL", AmountSpec::Shares(Decimal::new(100, 0)))
+            .limit(Decimal::new(10129, 3))
+            .build()
+            .unwrap();
+        let quantized = intent.quantize(Side::Sell, Decimal::new(1, 2), Decimal::new(1, 0));
+        assert_eq!(
+            quantized.order_type,
+            OrderType::Limit {
+                limit_price: Decimal::new(1013, 2)
+            }
+        );
+    }
 }